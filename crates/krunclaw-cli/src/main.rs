@@ -1,14 +1,18 @@
 use std::path::PathBuf;
 
-use anyhow::{Context, Result};
+use anyhow::{Context, Result, bail};
 use clap::{Args, Parser, Subcommand};
+use krunclaw_runtime::build::{BuildConfig, build_image, parse_build_fs_type};
 use krunclaw_runtime::doctor::run_doctor;
 use krunclaw_runtime::image::{
     FetchConfig, ImageConfig, ensure_image, fetch_ubuntu_image, image_disk_path, inspect_image,
 };
+use krunclaw_runtime::provision::load_provision_spec;
 use krunclaw_runtime::run::{
-    RunConfig, default_state_dir, parse_disk_format, parse_publish, run_openclaw,
+    RunConfig, default_env_overrides, default_state_dir, parse_disk_format, parse_publish,
+    run_openclaw,
 };
+use libkrun_sys::{EnvPolicy, EnvSpec};
 
 #[derive(Parser, Debug)]
 #[command(
@@ -77,6 +81,42 @@ struct RunCommand {
 
     #[arg(long)]
     arch: Option<String>,
+
+    /// Skip SHA256SUMS manifest verification of the Ubuntu release image when
+    /// auto-fetching. An --image-url source is never checked against a
+    /// manifest, with or without this flag.
+    #[arg(long)]
+    no_verify: bool,
+
+    /// YAML/TOML file describing first-boot provisioning (packages, files,
+    /// commands, env) to apply before `exec openclaw`.
+    #[arg(long)]
+    provision: Option<PathBuf>,
+
+    /// Host environment variables to pass through to the guest exec.
+    #[arg(long = "env-inherit", default_value = "none")]
+    env_inherit: String,
+
+    /// With --env-inherit=allowlist, a variable name to pass through. May be
+    /// repeated.
+    #[arg(long = "env-allow")]
+    env_allow: Vec<String>,
+
+    /// With --env-inherit=denylist, a variable name to drop. May be
+    /// repeated.
+    #[arg(long = "env-deny")]
+    env_deny: Vec<String>,
+
+    /// Explicit KEY=VALUE to set in the guest exec, overriding anything
+    /// inherited. May be repeated.
+    #[arg(long = "env")]
+    env: Vec<String>,
+
+    /// Run in the foreground sharing the host's controlling terminal: the
+    /// terminal is switched to raw mode for the life of the VM. Has no
+    /// effect when stdin isn't a tty.
+    #[arg(long)]
+    interactive: bool,
 }
 
 #[derive(Args, Debug)]
@@ -98,6 +138,7 @@ struct ImageCommand {
 enum ImageAction {
     Inspect(ImageInspect),
     Fetch(ImageFetch),
+    Build(ImageBuild),
 }
 
 #[derive(Args, Debug)]
@@ -128,6 +169,38 @@ struct ImageFetch {
 
     #[arg(long)]
     force: bool,
+
+    /// Skip SHA256SUMS manifest verification of the Ubuntu release image. A
+    /// --url source is never checked against a manifest, with or without
+    /// this flag.
+    #[arg(long)]
+    no_verify: bool,
+}
+
+#[derive(Args, Debug)]
+struct ImageBuild {
+    #[arg(long, default_value = "default")]
+    image: String,
+
+    #[arg(long)]
+    disk: Option<PathBuf>,
+
+    /// Size of the new disk image, in MiB.
+    #[arg(long = "size-mib", default_value_t = 1024)]
+    size_mib: u64,
+
+    /// Host directory whose contents are written into the disk image.
+    #[arg(long)]
+    source: PathBuf,
+
+    #[arg(long = "fs-type", default_value = "fat32")]
+    fs_type: String,
+
+    #[arg(long)]
+    label: Option<String>,
+
+    #[arg(long)]
+    force: bool,
 }
 
 fn main() -> Result<()> {
@@ -168,6 +241,7 @@ fn cmd_run(args: RunCommand) -> Result<()> {
                 ubuntu_date: args.ubuntu_date.clone(),
                 arch: args.arch.clone(),
                 force: false,
+                no_verify: args.no_verify,
             })?
         }
         Err(err) => return Err(err),
@@ -178,6 +252,14 @@ fn cmd_run(args: RunCommand) -> Result<()> {
         publish.push(parse_publish(&item)?);
     }
 
+    let provision = args
+        .provision
+        .as_deref()
+        .map(load_provision_spec)
+        .transpose()?;
+
+    let env = build_env_spec(&args.env_inherit, args.env_allow, args.env_deny, args.env)?;
+
     let root_fstype = if args.root_fstype.eq_ignore_ascii_case("auto") {
         Some("auto".to_string())
     } else {
@@ -196,11 +278,38 @@ fn cmd_run(args: RunCommand) -> Result<()> {
         state_dir,
         gateway_port: args.port,
         additional_publish: publish,
+        provision,
+        env,
+        interactive: args.interactive,
     };
 
     run_openclaw(&run_cfg)
 }
 
+fn build_env_spec(
+    inherit: &str,
+    allow: Vec<String>,
+    deny: Vec<String>,
+    overrides: Vec<String>,
+) -> Result<EnvSpec> {
+    let policy = match inherit.to_ascii_lowercase().as_str() {
+        "none" => EnvPolicy::InheritNone,
+        "all" => EnvPolicy::InheritAll,
+        "allowlist" => EnvPolicy::InheritAllowlist(allow),
+        "denylist" => EnvPolicy::InheritDenylist(deny),
+        other => bail!("unsupported --env-inherit value '{other}', expected none|all|allowlist|denylist"),
+    };
+
+    let mut spec = default_env_overrides().with_policy(policy);
+    for entry in overrides {
+        let (key, value) = entry
+            .split_once('=')
+            .with_context(|| format!("invalid --env value '{entry}', expected KEY=VALUE"))?;
+        spec = spec.with_override(key, value);
+    }
+    Ok(spec)
+}
+
 fn cmd_doctor(args: DoctorCommand) -> Result<()> {
     let disk_path = match args.disk {
         Some(path) => path,
@@ -250,6 +359,7 @@ fn cmd_image(args: ImageCommand) -> Result<()> {
                 ubuntu_date: fetch.ubuntu_date,
                 arch: fetch.arch,
                 force: fetch.force,
+                no_verify: fetch.no_verify,
             })?;
             println!("image fetch complete");
             println!("image: {}", status.image);
@@ -257,5 +367,21 @@ fn cmd_image(args: ImageCommand) -> Result<()> {
             println!("exists: {}", status.exists);
             Ok(())
         }
+        ImageAction::Build(build) => {
+            let status = build_image(&BuildConfig {
+                image: build.image,
+                disk: build.disk,
+                size_mib: build.size_mib,
+                source_dir: build.source,
+                fs_type: parse_build_fs_type(&build.fs_type)?,
+                label: build.label,
+                force: build.force,
+            })?;
+            println!("image build complete");
+            println!("image: {}", status.image);
+            println!("disk: {}", status.disk_path.display());
+            println!("exists: {}", status.exists);
+            Ok(())
+        }
     }
 }
@@ -0,0 +1,178 @@
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, bail};
+use fatfs::{Dir, FileSystem, FormatVolumeOptions, FsOptions, LossyOemCpConverter, NullTimeProvider};
+use fscommon::BufStream;
+
+use crate::image::{ImageConfig, ImageStatus, inspect_image};
+
+/// Filesystems `krunclaw image build` knows how to format. FAT32 is the only
+/// one supported today since it's what `fatfs` gives us for free without
+/// shelling out to `mkfs`; more variants can be added as the match arm grows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BuildFsType {
+    Fat32,
+}
+
+pub fn parse_build_fs_type(value: &str) -> Result<BuildFsType> {
+    match value.to_ascii_lowercase().as_str() {
+        "fat32" | "vfat" => Ok(BuildFsType::Fat32),
+        other => bail!("unsupported filesystem type '{other}', expected fat32"),
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct BuildConfig {
+    pub image: String,
+    pub disk: Option<PathBuf>,
+    pub size_mib: u64,
+    pub source_dir: PathBuf,
+    pub fs_type: BuildFsType,
+    pub label: Option<String>,
+    pub force: bool,
+}
+
+/// Assembles a bootable rootfs disk from a host directory, for building
+/// images on air-gapped hosts that can't `image fetch`. The result is
+/// registered at the same `image_disk_path` cache location fetched images
+/// use, so `ensure_image`/`run` and `image inspect` pick it up the same way.
+pub fn build_image(config: &BuildConfig) -> Result<ImageStatus> {
+    if config.size_mib == 0 {
+        bail!("--size-mib must be greater than zero");
+    }
+    if !config.source_dir.is_dir() {
+        bail!(
+            "source directory does not exist or is not a directory: {}",
+            config.source_dir.display()
+        );
+    }
+
+    let status = inspect_image(&ImageConfig {
+        image: config.image.clone(),
+        disk: config.disk.clone(),
+    })?;
+
+    if status.exists && !config.force {
+        bail!(
+            "disk image already exists at {} (use --force to replace)",
+            status.disk_path.display()
+        );
+    }
+
+    if let Some(parent) = status.disk_path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("failed to create {}", parent.display()))?;
+    }
+
+    let temp_path = status.disk_path.with_extension("img.building");
+    if temp_path.exists() {
+        fs::remove_file(&temp_path)
+            .with_context(|| format!("failed to remove {}", temp_path.display()))?;
+    }
+
+    match format_and_populate(&temp_path, config) {
+        Ok(()) => {}
+        Err(err) => {
+            let _ = fs::remove_file(&temp_path);
+            return Err(err);
+        }
+    }
+
+    if status.disk_path.exists() {
+        fs::remove_file(&status.disk_path).with_context(|| {
+            format!(
+                "failed to remove existing disk at {}",
+                status.disk_path.display()
+            )
+        })?;
+    }
+    fs::rename(&temp_path, &status.disk_path)
+        .with_context(|| format!("failed to move built image to {}", status.disk_path.display()))?;
+
+    inspect_image(&ImageConfig {
+        image: config.image.clone(),
+        disk: Some(status.disk_path.clone()),
+    })
+}
+
+fn format_and_populate(disk_path: &Path, config: &BuildConfig) -> Result<()> {
+    let BuildFsType::Fat32 = config.fs_type;
+
+    let file = File::create(disk_path)
+        .with_context(|| format!("failed to create {}", disk_path.display()))?;
+    file.set_len(config.size_mib * 1024 * 1024)
+        .with_context(|| format!("failed to size {} to {} MiB", disk_path.display(), config.size_mib))?;
+
+    let mut buffered = BufStream::new(file);
+
+    let mut format_opts = FormatVolumeOptions::new();
+    if let Some(label) = &config.label {
+        format_opts = format_opts.volume_label(fat_volume_label(label));
+    }
+    fatfs::format_volume(&mut buffered, format_opts)
+        .with_context(|| format!("failed to format {} as fat32", disk_path.display()))?;
+
+    let filesystem = FileSystem::new(&mut buffered, FsOptions::new())
+        .context("failed to open freshly formatted filesystem")?;
+    copy_dir_into_fat(&config.source_dir, &filesystem.root_dir())
+        .context("failed to populate built image")?;
+
+    Ok(())
+}
+
+/// FAT volume labels are exactly 11 bytes, space-padded and uppercased.
+fn fat_volume_label(label: &str) -> [u8; 11] {
+    let mut bytes = [b' '; 11];
+    for (slot, byte) in bytes.iter_mut().zip(label.to_ascii_uppercase().bytes()) {
+        *slot = byte;
+    }
+    bytes
+}
+
+fn copy_dir_into_fat<IO: fatfs::ReadWriteSeek>(
+    host_dir: &Path,
+    fat_dir: &Dir<'_, IO, NullTimeProvider, LossyOemCpConverter>,
+) -> Result<()> {
+    for entry in fs::read_dir(host_dir)
+        .with_context(|| format!("failed to read {}", host_dir.display()))?
+    {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let file_type = entry.file_type()?;
+
+        if file_type.is_dir() {
+            let sub_dir = fat_dir
+                .create_dir(&name)
+                .with_context(|| format!("failed to create guest dir '{name}'"))?;
+            copy_dir_into_fat(&entry.path(), &sub_dir)?;
+        } else if file_type.is_file() {
+            let mut dest = fat_dir
+                .create_file(&name)
+                .with_context(|| format!("failed to create guest file '{name}'"))?;
+            let mut src = File::open(entry.path())
+                .with_context(|| format!("failed to open {}", entry.path().display()))?;
+            std::io::copy(&mut src, &mut dest)
+                .with_context(|| format!("failed to write guest file '{name}'"))?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn build_fs_type_accepts_known_values() {
+        assert!(matches!(parse_build_fs_type("fat32"), Ok(BuildFsType::Fat32)));
+        assert!(matches!(parse_build_fs_type("VFAT"), Ok(BuildFsType::Fat32)));
+        assert!(parse_build_fs_type("ext4").is_err());
+    }
+
+    #[test]
+    fn volume_label_is_padded_and_uppercased() {
+        assert_eq!(&fat_volume_label("krun"), b"KRUN       ");
+        assert_eq!(&fat_volume_label("ROOTFSLABEL"), b"ROOTFSLABEL");
+    }
+}
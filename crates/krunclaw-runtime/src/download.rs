@@ -0,0 +1,213 @@
+use std::fmt;
+use std::fs::File;
+use std::io::{self, IsTerminal, Read, Write};
+use std::path::Path;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use sha2::{Digest, Sha256};
+
+const MAX_ATTEMPTS: u32 = 3;
+const RETRY_DELAY: Duration = Duration::from_secs(2);
+const BUF_SIZE: usize = 256 * 1024;
+
+#[derive(Debug, Clone)]
+pub struct DownloadOutcome {
+    pub bytes_written: u64,
+    pub sha256: String,
+}
+
+/// Distinguishes a checksum mismatch from a transient request/IO failure so
+/// the retry loop in `download_to_path` can tell them apart: re-fetching the
+/// same bytes from the same URL would not change the outcome, so a mismatch
+/// is returned immediately instead of being retried.
+#[derive(Debug)]
+struct ChecksumMismatch {
+    url: String,
+    expected: String,
+    actual: String,
+}
+
+impl fmt::Display for ChecksumMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "sha256 mismatch for {}: expected {}, got {}",
+            self.url, self.expected, self.actual
+        )
+    }
+}
+
+impl std::error::Error for ChecksumMismatch {}
+
+/// Streams `url` to `dest_path`, hashing bytes as they land on disk instead of
+/// reading the file back afterwards. If `expected_sha256` is set, a mismatch
+/// deletes the partial file and returns an error rather than leaving a
+/// corrupt image behind. Transient request/IO failures are retried with a
+/// fixed backoff; a checksum mismatch is not retried since re-fetching the
+/// same bytes would not change the outcome.
+pub fn download_to_path(
+    url: &str,
+    dest_path: &Path,
+    expected_sha256: Option<&str>,
+) -> Result<DownloadOutcome> {
+    let mut last_error = None;
+    for attempt in 1..=MAX_ATTEMPTS {
+        match download_once(url, dest_path, expected_sha256) {
+            Ok(outcome) => return Ok(outcome),
+            Err(err) => {
+                let _ = std::fs::remove_file(dest_path);
+                if err.downcast_ref::<ChecksumMismatch>().is_some() {
+                    return Err(err);
+                }
+                if attempt < MAX_ATTEMPTS {
+                    eprintln!(
+                        "download attempt {attempt}/{MAX_ATTEMPTS} failed: {err}; retrying in {}s",
+                        RETRY_DELAY.as_secs()
+                    );
+                    std::thread::sleep(RETRY_DELAY);
+                }
+                last_error = Some(err);
+            }
+        }
+    }
+    Err(last_error.expect("loop runs at least once"))
+}
+
+fn download_once(url: &str, dest_path: &Path, expected_sha256: Option<&str>) -> Result<DownloadOutcome> {
+    let response = ureq::get(url)
+        .call()
+        .with_context(|| format!("request failed for {url}"))?;
+
+    let total_len = response
+        .header("Content-Length")
+        .and_then(|value| value.parse::<u64>().ok());
+
+    let mut reader = response.into_reader();
+    let mut file = File::create(dest_path)
+        .with_context(|| format!("failed to create {}", dest_path.display()))?;
+
+    let mut hasher = Sha256::new();
+    let mut buf = [0u8; BUF_SIZE];
+    let mut written: u64 = 0;
+
+    loop {
+        let read = reader
+            .read(&mut buf)
+            .with_context(|| format!("failed reading response body from {url}"))?;
+        if read == 0 {
+            break;
+        }
+
+        let chunk = &buf[..read];
+        hasher.update(chunk);
+        file.write_all(chunk)
+            .with_context(|| format!("failed writing {}", dest_path.display()))?;
+        written += read as u64;
+        report_progress(written, total_len);
+    }
+    file.flush()
+        .with_context(|| format!("failed to flush {}", dest_path.display()))?;
+    if io::stderr().is_terminal() {
+        eprintln!();
+    }
+
+    let digest = format!("{:x}", hasher.finalize());
+    if let Some(expected) = expected_sha256
+        && !digest.eq_ignore_ascii_case(expected)
+    {
+        let _ = std::fs::remove_file(dest_path);
+        return Err(ChecksumMismatch {
+            url: url.to_string(),
+            expected: expected.to_string(),
+            actual: digest,
+        }
+        .into());
+    }
+
+    Ok(DownloadOutcome {
+        bytes_written: written,
+        sha256: digest,
+    })
+}
+
+fn report_progress(written: u64, total: Option<u64>) {
+    if !io::stderr().is_terminal() {
+        return;
+    }
+    match total {
+        Some(total) if total > 0 => {
+            let pct = (written as f64 / total as f64) * 100.0;
+            eprint!("\r  downloading... {written}/{total} bytes ({pct:.1}%)");
+        }
+        _ => eprint!("\r  downloading... {written} bytes"),
+    }
+    let _ = io::stderr().flush();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::BufRead;
+    use std::net::TcpListener;
+    use std::time::Instant;
+
+    #[test]
+    fn malformed_url_fails_after_retries_and_leaves_no_file() {
+        let dir = std::env::temp_dir().join(format!("krunclaw-download-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let dest = dir.join("payload.bin");
+
+        // A malformed URL fails before any bytes are written, which is enough
+        // to exercise the retry-then-error path without needing network access.
+        // Actual digest-mismatch coverage lives in `checksum_mismatch_is_not_retried`.
+        let err = download_to_path("not-a-url", &dest, Some("deadbeef")).unwrap_err();
+        assert!(!dest.exists());
+        assert!(err.to_string().contains("request failed"));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    /// Serves a single fixed HTTP response on localhost for one connection.
+    fn serve_once(body: &'static [u8]) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        std::thread::spawn(move || {
+            if let Ok((stream, _)) = listener.accept() {
+                let mut reader = io::BufReader::new(&stream);
+                let mut request_line = String::new();
+                let _ = reader.read_line(&mut request_line);
+                let mut line = String::new();
+                while reader.read_line(&mut line).unwrap_or(0) > 2 {
+                    line.clear();
+                }
+                let mut writer = &stream;
+                let _ = write!(
+                    writer,
+                    "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                    body.len()
+                );
+                let _ = writer.write_all(body);
+            }
+        });
+        format!("http://{addr}")
+    }
+
+    #[test]
+    fn checksum_mismatch_is_not_retried() {
+        let dir = std::env::temp_dir().join(format!("krunclaw-download-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let dest = dir.join("payload.bin");
+
+        let url = serve_once(b"hello world");
+        let started = Instant::now();
+        let err = download_to_path(&url, &dest, Some("deadbeef")).unwrap_err();
+        // A retried mismatch would sleep RETRY_DELAY between every attempt;
+        // returning well under that proves the loop bailed on the first try.
+        assert!(started.elapsed() < RETRY_DELAY);
+        assert!(!dest.exists());
+        assert!(err.to_string().contains("sha256 mismatch"));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -1,9 +1,11 @@
 use std::fs;
 use std::path::PathBuf;
-use std::process::Command;
 
 use anyhow::{Context, Result, bail};
 
+use crate::download::download_to_path;
+use crate::ubuntu_signing;
+
 #[derive(Debug, Clone)]
 pub struct ImageConfig {
     pub image: String,
@@ -25,6 +27,7 @@ pub struct FetchConfig {
     pub ubuntu_date: Option<String>,
     pub arch: Option<String>,
     pub force: bool,
+    pub no_verify: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -112,18 +115,8 @@ pub fn fetch_ubuntu_image(config: &FetchConfig) -> Result<ImageStatus> {
 
     let mut last_error = None;
     for source in &sources {
-        match download_to_path(&source.url, &temp_path) {
-            Ok(()) => {
-                if let Some(digest) = &source.sha256 {
-                    verify_sha256(&temp_path, digest).with_context(|| {
-                        format!(
-                            "sha256 verification failed for {} from {}",
-                            temp_path.display(),
-                            source.url
-                        )
-                    })?;
-                }
-
+        match download_to_path(&source.url, &temp_path, source.sha256.as_deref()) {
+            Ok(_outcome) => {
                 if status.disk_path.exists() {
                     fs::remove_file(&status.disk_path).with_context(|| {
                         format!(
@@ -160,6 +153,11 @@ pub fn fetch_ubuntu_image(config: &FetchConfig) -> Result<ImageStatus> {
     );
 }
 
+/// Release directory pinned as the default before falling back to the
+/// unversioned `release/` alias, which Canonical repoints at whatever the
+/// latest point release is.
+const DEFAULT_UBUNTU_RELEASE_DATE: &str = "20251213";
+
 fn resolve_sources(config: &FetchConfig) -> Result<Vec<ImageSource>> {
     if let Some(custom_url) = &config.url {
         return Ok(vec![ImageSource {
@@ -174,51 +172,91 @@ fn resolve_sources(config: &FetchConfig) -> Result<Vec<ImageSource>> {
         .unwrap_or_else(|| std::env::consts::ARCH.to_string());
     let suffix = ubuntu_suffix_for_arch(&arch)
         .ok_or_else(|| anyhow::anyhow!("unsupported arch '{}' for ubuntu cloud image", arch))?;
-
-    if let Some(date) = &config.ubuntu_date {
-        return Ok(vec![ImageSource {
-            url: format!(
-                "https://cloud-images.ubuntu.com/releases/noble/release-{date}/ubuntu-24.04-server-cloudimg-{suffix}.img"
-            ),
-            sha256: None,
-        }]);
+    let filename = format!("ubuntu-24.04-server-cloudimg-{suffix}.img");
+
+    let mut manifest_error = None;
+    let sources: Vec<ImageSource> = release_dirs_for(config.ubuntu_date.as_deref())
+        .into_iter()
+        .filter_map(|release_dir| {
+            let url = format!("{release_dir}/{filename}");
+            let sha256 = if config.no_verify {
+                None
+            } else {
+                match manifest_digest_for(&release_dir, &filename).with_context(|| {
+                    format!(
+                        "failed to verify {filename} against the signed SHA256SUMS at {release_dir} \
+                         (pass --no-verify to skip, e.g. for a mirrored release dir)"
+                    )
+                }) {
+                    Ok(digest) => Some(digest),
+                    Err(err) => {
+                        manifest_error = Some(err);
+                        return None;
+                    }
+                }
+            };
+            Some(ImageSource { url, sha256 })
+        })
+        .collect();
+
+    match manifest_error {
+        Some(err) if sources.is_empty() => Err(err),
+        _ => Ok(sources),
     }
-
-    Ok(default_lima_ubuntu_sources_for_suffix(suffix))
 }
 
-fn default_lima_ubuntu_sources_for_suffix(suffix: &str) -> Vec<ImageSource> {
-    let primary = match suffix {
-        "amd64" => Some(ImageSource {
-            url: "https://cloud-images.ubuntu.com/releases/noble/release-20251213/ubuntu-24.04-server-cloudimg-amd64.img"
-                .to_string(),
-            sha256: Some(
-                "2b5f90ffe8180def601c021c874e55d8303e8bcbfc66fee2b94414f43ac5eb1f".to_string(),
-            ),
-        }),
-        "arm64" => Some(ImageSource {
-            url: "https://cloud-images.ubuntu.com/releases/noble/release-20251213/ubuntu-24.04-server-cloudimg-arm64.img"
-                .to_string(),
-            sha256: Some(
-                "a40713938d74aaec811f74cb1fa8bfcb535d22e26b2a0ca1cc90ad9db898feb9".to_string(),
-            ),
-        }),
-        _ => None,
-    };
-
-    let fallback = ImageSource {
-        url: format!(
-            "https://cloud-images.ubuntu.com/releases/noble/release/ubuntu-24.04-server-cloudimg-{suffix}.img"
-        ),
-        sha256: None,
-    };
-
-    match primary {
-        Some(primary) => vec![primary, fallback],
-        None => vec![fallback],
+/// Candidate `cloud-images.ubuntu.com` release directories to try, in order.
+/// An explicit `--ubuntu-date` pins a single directory; otherwise we try the
+/// pinned default date first and fall back to the unversioned alias.
+fn release_dirs_for(ubuntu_date: Option<&str>) -> Vec<String> {
+    match ubuntu_date {
+        Some(date) => vec![format!(
+            "https://cloud-images.ubuntu.com/releases/noble/release-{date}"
+        )],
+        None => vec![
+            format!("https://cloud-images.ubuntu.com/releases/noble/release-{DEFAULT_UBUNTU_RELEASE_DATE}"),
+            "https://cloud-images.ubuntu.com/releases/noble/release".to_string(),
+        ],
     }
 }
 
+/// Downloads and GPG-verifies `SHA256SUMS`/`SHA256SUMS.gpg` from
+/// `release_dir`, returning the digest recorded for `filename`.
+fn manifest_digest_for(release_dir: &str, filename: &str) -> Result<String> {
+    let work_dir = std::env::temp_dir().join(format!("krunclaw-manifest-{}", std::process::id()));
+    fs::create_dir_all(&work_dir)
+        .with_context(|| format!("failed to create {}", work_dir.display()))?;
+    let manifest_path = work_dir.join("SHA256SUMS");
+    let signature_path = work_dir.join("SHA256SUMS.gpg");
+
+    let result = (|| -> Result<String> {
+        download_to_path(&format!("{release_dir}/SHA256SUMS"), &manifest_path, None)
+            .context("failed to fetch SHA256SUMS")?;
+        download_to_path(
+            &format!("{release_dir}/SHA256SUMS.gpg"),
+            &signature_path,
+            None,
+        )
+        .context("failed to fetch SHA256SUMS.gpg")?;
+
+        let manifest_bytes = fs::read(&manifest_path).context("failed to read SHA256SUMS")?;
+        let signature_bytes =
+            fs::read(&signature_path).context("failed to read SHA256SUMS.gpg")?;
+
+        let signing_key = ubuntu_signing::load_trusted_signing_key()?;
+        ubuntu_signing::verify_manifest_signature(&manifest_bytes, &signature_bytes, &signing_key)?;
+
+        let manifest_text =
+            String::from_utf8(manifest_bytes).context("SHA256SUMS is not valid UTF-8")?;
+        ubuntu_signing::find_digest(&manifest_text, filename)
+            .map(str::to_string)
+            .ok_or_else(|| anyhow::anyhow!("{filename} not listed in SHA256SUMS"))
+    })();
+
+    let _ = fs::remove_dir_all(&work_dir);
+    result
+}
+
 fn ubuntu_suffix_for_arch(arch: &str) -> Option<&'static str> {
     match arch {
         "x86_64" | "amd64" => Some("amd64"),
@@ -231,49 +269,6 @@ fn ubuntu_suffix_for_arch(arch: &str) -> Option<&'static str> {
     }
 }
 
-fn download_to_path(url: &str, path: &PathBuf) -> Result<()> {
-    let output = Command::new("curl")
-        .arg("-fL")
-        .arg("--retry")
-        .arg("3")
-        .arg("--retry-delay")
-        .arg("2")
-        .arg("-o")
-        .arg(path)
-        .arg(url)
-        .output()
-        .with_context(|| format!("failed to spawn curl for {url}"))?;
-
-    if output.status.success() {
-        Ok(())
-    } else {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        bail!("curl failed for {url}: {}", stderr.trim())
-    }
-}
-
-fn verify_sha256(path: &PathBuf, expected: &str) -> Result<()> {
-    let output = Command::new("shasum")
-        .arg("-a")
-        .arg("256")
-        .arg(path)
-        .output()
-        .with_context(|| format!("failed to spawn shasum for {}", path.display()))?;
-
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        bail!("shasum failed: {}", stderr.trim());
-    }
-
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let actual = stdout.split_whitespace().next().unwrap_or_default();
-    if actual.eq_ignore_ascii_case(expected) {
-        Ok(())
-    } else {
-        bail!("expected sha256 {expected}, got {actual}")
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -294,10 +289,18 @@ mod tests {
     }
 
     #[test]
-    fn ubuntu_sources_include_fallback() {
-        let sources = default_lima_ubuntu_sources_for_suffix("amd64");
-        assert_eq!(sources.len(), 2);
-        assert!(sources[0].url.contains("release-20251213"));
-        assert!(sources[1].url.contains("/release/"));
+    fn release_dirs_default_to_pinned_then_fallback() {
+        let dirs = release_dirs_for(None);
+        assert_eq!(dirs.len(), 2);
+        assert!(dirs[0].contains("release-20251213"));
+        assert!(dirs[1].ends_with("/release"));
+    }
+
+    #[test]
+    fn release_dirs_pin_explicit_date() {
+        let dirs = release_dirs_for(Some("20260101"));
+        assert_eq!(dirs, vec![
+            "https://cloud-images.ubuntu.com/releases/noble/release-20260101".to_string()
+        ]);
     }
 }
@@ -0,0 +1,7 @@
+pub mod build;
+pub mod doctor;
+pub mod download;
+pub mod image;
+pub mod provision;
+pub mod run;
+mod ubuntu_signing;
@@ -0,0 +1,186 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result, bail};
+use serde::Deserialize;
+
+/// First-boot provisioning, modeled loosely on cloud-init/Ignition: packages
+/// to install, files to drop into the guest, and extra commands/env to run
+/// before `exec openclaw`. Loaded from a user-supplied YAML or TOML file so
+/// guests can be customized (pinned openclaw version, CA certs, a richer
+/// openclaw config) without patching this crate.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ProvisionSpec {
+    #[serde(default)]
+    pub packages: Vec<String>,
+    #[serde(default)]
+    pub files: Vec<ProvisionFile>,
+    #[serde(default)]
+    pub commands: Vec<String>,
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ProvisionFile {
+    /// Absolute path inside the guest.
+    pub path: String,
+    #[serde(default)]
+    pub content: String,
+    /// Octal permission string, e.g. "0644".
+    #[serde(default = "default_mode")]
+    pub mode: String,
+}
+
+fn default_mode() -> String {
+    "0644".to_string()
+}
+
+/// Reads and validates a provisioning file, inferring YAML vs TOML from its
+/// extension.
+pub fn load_provision_spec(path: &Path) -> Result<ProvisionSpec> {
+    let raw = fs::read_to_string(path)
+        .with_context(|| format!("failed to read provisioning file {}", path.display()))?;
+
+    let spec: ProvisionSpec = match path.extension().and_then(|ext| ext.to_str()) {
+        Some("yaml") | Some("yml") => serde_yaml::from_str(&raw)
+            .with_context(|| format!("failed to parse {} as YAML", path.display()))?,
+        Some("toml") => toml::from_str(&raw)
+            .with_context(|| format!("failed to parse {} as TOML", path.display()))?,
+        other => bail!(
+            "unsupported provisioning file extension '{}' for {}; expected .yaml, .yml, or .toml",
+            other.unwrap_or(""),
+            path.display()
+        ),
+    };
+
+    validate(&spec)?;
+    Ok(spec)
+}
+
+fn validate(spec: &ProvisionSpec) -> Result<()> {
+    for file in &spec.files {
+        if !file.path.starts_with('/') {
+            bail!("provisioning file path must be absolute: '{}'", file.path);
+        }
+        parse_octal_mode(&file.mode)
+            .with_context(|| format!("invalid mode '{}' for '{}'", file.mode, file.path))?;
+    }
+    for (name, _) in &spec.env {
+        if name.is_empty() || name.contains('=') {
+            bail!("invalid provisioning env var name: '{name}'");
+        }
+    }
+    Ok(())
+}
+
+fn parse_octal_mode(mode: &str) -> Result<u32> {
+    u32::from_str_radix(mode.trim_start_matches("0o"), 8).with_context(|| format!("'{mode}' is not a valid octal mode"))
+}
+
+/// Renders the provisioning steps as POSIX `sh` fragments, to be spliced into
+/// the guest entrypoint script ahead of `exec openclaw`.
+pub fn render_shell_fragment(spec: &ProvisionSpec) -> String {
+    let mut script = String::new();
+
+    if !spec.packages.is_empty() {
+        script.push_str("if command -v apt-get >/dev/null 2>&1; then\n");
+        script.push_str("  export DEBIAN_FRONTEND=noninteractive\n");
+        script.push_str("  apt-get update\n");
+        script.push_str(&format!(
+            "  apt-get install -y --no-install-recommends {}\n",
+            spec.packages.join(" ")
+        ));
+        script.push_str("else\n");
+        script.push_str("  echo 'error: provisioning requested packages but apt-get is unavailable' >&2\n");
+        script.push_str("  exit 1\n");
+        script.push_str("fi\n\n");
+    }
+
+    for file in &spec.files {
+        let mode = parse_octal_mode(&file.mode).unwrap_or(0o644);
+        script.push_str(&format!("mkdir -p \"$(dirname '{}')\"\n", file.path));
+        script.push_str(&format!("cat > '{}' <<'KRUNCLAW_PROVISION_EOF'\n", file.path));
+        script.push_str(&file.content);
+        if !file.content.ends_with('\n') {
+            script.push('\n');
+        }
+        script.push_str("KRUNCLAW_PROVISION_EOF\n");
+        script.push_str(&format!("chmod {:o} '{}'\n\n", mode, file.path));
+    }
+
+    for (key, value) in &spec.env {
+        script.push_str(&format!("export {key}={}\n", shell_quote(value)));
+    }
+    if !spec.env.is_empty() {
+        script.push('\n');
+    }
+
+    for command in &spec.commands {
+        script.push_str(command);
+        script.push('\n');
+    }
+    if !spec.commands.is_empty() {
+        script.push('\n');
+    }
+
+    script
+}
+
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_relative_file_paths() {
+        let spec = ProvisionSpec {
+            files: vec![ProvisionFile {
+                path: "etc/motd".to_string(),
+                content: String::new(),
+                mode: default_mode(),
+            }],
+            ..Default::default()
+        };
+        let error = validate(&spec).unwrap_err();
+        assert!(error.to_string().contains("must be absolute"));
+    }
+
+    #[test]
+    fn rejects_invalid_mode() {
+        let spec = ProvisionSpec {
+            files: vec![ProvisionFile {
+                path: "/etc/motd".to_string(),
+                content: String::new(),
+                mode: "rwxr".to_string(),
+            }],
+            ..Default::default()
+        };
+        assert!(validate(&spec).is_err());
+    }
+
+    #[test]
+    fn renders_packages_files_env_and_commands() {
+        let mut env = BTreeMap::new();
+        env.insert("FOO".to_string(), "bar baz".to_string());
+        let spec = ProvisionSpec {
+            packages: vec!["ca-certificates".to_string()],
+            files: vec![ProvisionFile {
+                path: "/etc/motd".to_string(),
+                content: "hello".to_string(),
+                mode: "0644".to_string(),
+            }],
+            commands: vec!["touch /tmp/ran-provisioning".to_string()],
+            env,
+        };
+        let rendered = render_shell_fragment(&spec);
+        assert!(rendered.contains("apt-get install -y --no-install-recommends ca-certificates"));
+        assert!(rendered.contains("cat > '/etc/motd'"));
+        assert!(rendered.contains("export FOO='bar baz'"));
+        assert!(rendered.contains("touch /tmp/ran-provisioning"));
+    }
+}
@@ -1,7 +1,11 @@
+use std::fs::File;
+use std::io::Read;
 use std::path::{Path, PathBuf};
 
 use anyhow::{Context, Result, bail};
-use libkrun_sys::{DiskImageFormat, ExecSpec, LibKrun, RootSpec, RunSpec};
+use libkrun_sys::{DiskImageFormat, EnvSpec, ExecSpec, LibKrun, RootSpec, RunSpec};
+
+use crate::provision::ProvisionSpec;
 
 #[derive(Debug, Clone)]
 pub struct PublishSpec {
@@ -30,6 +34,22 @@ pub struct RunConfig {
     pub state_dir: PathBuf,
     pub gateway_port: u16,
     pub additional_publish: Vec<PublishSpec>,
+    pub provision: Option<ProvisionSpec>,
+    pub env: EnvSpec,
+    pub interactive: bool,
+}
+
+/// The env `krunclaw run` forces regardless of the host-inheritance policy,
+/// so the guest always has a sane `HOME`/`PATH` to fall back on.
+pub fn default_env_overrides() -> EnvSpec {
+    EnvSpec::default()
+        .with_override("HOME", "/root")
+        .with_override("USER", "root")
+        .with_override("SHELL", "/bin/sh")
+        .with_override(
+            "PATH",
+            "/usr/local/sbin:/usr/local/bin:/usr/sbin:/usr/bin:/sbin:/bin",
+        )
 }
 
 pub fn default_state_dir() -> Result<PathBuf> {
@@ -85,6 +105,11 @@ pub fn build_run_spec(config: &RunConfig) -> Result<RunSpec> {
     }
 
     let guest_config_path = "/tmp/krunclaw-openclaw.json";
+    let provision_fragment = config
+        .provision
+        .as_ref()
+        .map(crate::provision::render_shell_fragment)
+        .unwrap_or_default();
     let entrypoint_script = format!(
         r#"set -eu
 mkdir -p /workspace /root/.openclaw
@@ -121,7 +146,7 @@ cat > {guest_config_path} <<'JSON'
 }}
 JSON
 
-export HOME=/root
+{provision_fragment}export HOME=/root
 export OPENCLAW_CONFIG_PATH={guest_config_path}
 exec openclaw gateway --allow-unconfigured --port {gateway_port}
 "#,
@@ -131,12 +156,7 @@ exec openclaw gateway --allow-unconfigured --port {gateway_port}
     let exec = ExecSpec {
         exec_path: "/bin/sh".to_string(),
         argv: vec!["-c".to_string(), entrypoint_script],
-        env: vec![
-            "HOME=/root".to_string(),
-            "USER=root".to_string(),
-            "SHELL=/bin/sh".to_string(),
-            "PATH=/usr/local/sbin:/usr/local/bin:/usr/sbin:/usr/bin:/sbin:/bin".to_string(),
-        ],
+        env: config.env.build(std::env::vars()),
         workdir: "/".to_string(),
     };
 
@@ -160,6 +180,7 @@ exec openclaw gateway --allow-unconfigured --port {gateway_port}
         ],
         port_map,
         exec,
+        interactive: config.interactive,
     })
 }
 
@@ -178,24 +199,76 @@ fn resolve_disk_format(format: DiskFormatArg, disk_path: &Path) -> Result<DiskIm
     }
 }
 
+// qcow2: "QFI\xFB"
+const QCOW2_MAGIC: [u8; 4] = [0x51, 0x46, 0x49, 0xFB];
+// Sparse VMDK extent: "KDMV"
+const VMDK_SPARSE_MAGIC: [u8; 4] = [0x4B, 0x44, 0x4D, 0x56];
+// VMDK descriptor (text) file.
+const VMDK_DESCRIPTOR_MAGIC: &[u8] = b"# Disk DescriptorFile";
+const MIN_RAW_DISK_SIZE: u64 = 1024 * 1024;
+
+/// Detects a disk's format from its content rather than its extension: every
+/// `.img` in the wild might be a raw image, a qcow2 image, or a VMDK, and
+/// guessing wrong from the extension alone leads to a VM that fails to boot
+/// with no useful error. Magic bytes win whenever they're conclusive; the
+/// extension is only consulted when the file is too small to carry a magic
+/// header, or carries no known magic and is under 1 MiB (too small to be
+/// taken for a raw disk).
 fn guess_disk_format(disk_path: &Path) -> Result<DiskImageFormat> {
+    let metadata = std::fs::metadata(disk_path)
+        .with_context(|| format!("failed to stat {}", disk_path.display()))?;
+
+    let mut file = File::open(disk_path)
+        .with_context(|| format!("failed to open {} for format detection", disk_path.display()))?;
+
+    let mut header = [0u8; VMDK_DESCRIPTOR_MAGIC.len()];
+    let read = file
+        .read(&mut header)
+        .with_context(|| format!("failed to read {} for format detection", disk_path.display()))?;
+
+    if read < 4 {
+        return guess_disk_format_from_extension(disk_path).ok_or_else(|| unknown_format_error(disk_path));
+    }
+
+    if header[..4] == QCOW2_MAGIC {
+        return Ok(DiskImageFormat::Qcow2);
+    }
+    if header[..4] == VMDK_SPARSE_MAGIC {
+        return Ok(DiskImageFormat::Vmdk);
+    }
+    if read == VMDK_DESCRIPTOR_MAGIC.len() && header == *VMDK_DESCRIPTOR_MAGIC {
+        return Ok(DiskImageFormat::Vmdk);
+    }
+
+    if metadata.len() >= MIN_RAW_DISK_SIZE {
+        return Ok(DiskImageFormat::Raw);
+    }
+
+    guess_disk_format_from_extension(disk_path).ok_or_else(|| unknown_format_error(disk_path))
+}
+
+fn guess_disk_format_from_extension(disk_path: &Path) -> Option<DiskImageFormat> {
     let name = disk_path
         .file_name()
         .and_then(|value| value.to_str())
         .unwrap_or_default()
         .to_ascii_lowercase();
 
-    if name.ends_with(".qcow2") || name.ends_with(".img") {
-        return Ok(DiskImageFormat::Qcow2);
+    if name.ends_with(".qcow2") {
+        return Some(DiskImageFormat::Qcow2);
     }
     if name.ends_with(".vmdk") {
-        return Ok(DiskImageFormat::Vmdk);
+        return Some(DiskImageFormat::Vmdk);
     }
-    if name.ends_with(".raw") {
-        return Ok(DiskImageFormat::Raw);
+    if name.ends_with(".raw") || name.ends_with(".img") {
+        return Some(DiskImageFormat::Raw);
     }
 
-    bail!(
+    None
+}
+
+fn unknown_format_error(disk_path: &Path) -> anyhow::Error {
+    anyhow::anyhow!(
         "cannot auto-detect disk format from '{}'; pass --disk-format explicitly",
         disk_path.display()
     )
@@ -236,4 +309,42 @@ mod tests {
         ));
         assert!(matches!(parse_disk_format("vmdk"), Ok(DiskFormatArg::Vmdk)));
     }
+
+    fn write_temp_file(name: &str, contents: &[u8]) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("krunclaw-run-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(name);
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn detects_qcow2_by_magic_regardless_of_extension() {
+        let path = write_temp_file("disk.img", &[0x51, 0x46, 0x49, 0xFB, 0, 0, 0, 0]);
+        assert!(matches!(guess_disk_format(&path), Ok(DiskImageFormat::Qcow2)));
+    }
+
+    #[test]
+    fn detects_sparse_vmdk_by_magic() {
+        let path = write_temp_file("disk.bin", b"KDMV\0\0\0\0");
+        assert!(matches!(guess_disk_format(&path), Ok(DiskImageFormat::Vmdk)));
+    }
+
+    #[test]
+    fn detects_vmdk_descriptor_by_magic() {
+        let path = write_temp_file("disk.vmdk", b"# Disk DescriptorFile\nversion=1\n");
+        assert!(matches!(guess_disk_format(&path), Ok(DiskImageFormat::Vmdk)));
+    }
+
+    #[test]
+    fn falls_back_to_extension_when_too_small_for_magic() {
+        let path = write_temp_file("disk.raw", &[0, 1]);
+        assert!(matches!(guess_disk_format(&path), Ok(DiskImageFormat::Raw)));
+    }
+
+    #[test]
+    fn falls_back_to_raw_for_large_unrecognized_content() {
+        let path = write_temp_file("disk.img", &vec![0u8; MIN_RAW_DISK_SIZE as usize]);
+        assert!(matches!(guess_disk_format(&path), Ok(DiskImageFormat::Raw)));
+    }
 }
@@ -0,0 +1,131 @@
+use std::io;
+use std::os::unix::io::RawFd;
+use std::sync::{Arc, Mutex};
+
+use anyhow::{Context, Result};
+
+/// Prepares the controlling terminal for an interactive VM launch: switches
+/// it to raw mode for as long as the guard is alive, and starts a thread that
+/// handles `SIGWINCH`/`SIGINT`/`SIGTERM`. Dropping the guard restores the
+/// terminal but leaves that thread and the blocked signal mask in place.
+///
+/// Raw mode is restored on drop, including during a panic unwind, so a guest
+/// crash doesn't leave the host shell in cooked mode with no echo.
+/// `SIGINT`/`SIGTERM` restore it immediately and then exit the process:
+/// `krun_start_enter` blocks the calling thread for the life of the VM with
+/// no bound call to interrupt it, so the best teardown available here is
+/// leaving the terminal sane before the process goes away. `SIGWINCH`
+/// re-reads the host terminal size via `TIOCGWINSZ`; libkrun doesn't bind a
+/// call to forward that into the guest console yet, so `current_size`
+/// exposes it rather than silently dropping the signal.
+pub struct ConsoleGuard {
+    raw_mode: RawModeGuard,
+    size: Arc<Mutex<Option<libc::winsize>>>,
+}
+
+impl ConsoleGuard {
+    pub fn enable(fd: RawFd) -> Result<Self> {
+        let raw_mode = RawModeGuard::enable(fd)?;
+        let size = watch_signals(fd, raw_mode.original)?;
+        Ok(Self { raw_mode, size })
+    }
+
+    /// Most recently observed host terminal size, updated on `SIGWINCH`.
+    pub fn current_size(&self) -> Option<libc::winsize> {
+        self.size.lock().ok().and_then(|guard| *guard)
+    }
+}
+
+/// Switches the controlling terminal to raw mode for as long as the guard is
+/// alive, restoring the saved termios on drop. Drop still runs during a
+/// panic unwind, so a guest crash doesn't leave the host shell in cooked
+/// mode with no echo.
+pub struct RawModeGuard {
+    fd: RawFd,
+    original: libc::termios,
+}
+
+impl RawModeGuard {
+    pub fn enable(fd: RawFd) -> Result<Self> {
+        let mut original: libc::termios = unsafe { std::mem::zeroed() };
+        if unsafe { libc::tcgetattr(fd, &mut original) } != 0 {
+            return Err(io::Error::last_os_error()).context("tcgetattr failed");
+        }
+
+        let mut raw = original;
+        unsafe { libc::cfmakeraw(&mut raw) };
+        if unsafe { libc::tcsetattr(fd, libc::TCSANOW, &raw) } != 0 {
+            return Err(io::Error::last_os_error()).context("tcsetattr failed");
+        }
+
+        Ok(Self { fd, original })
+    }
+}
+
+impl Drop for RawModeGuard {
+    fn drop(&mut self) {
+        unsafe {
+            libc::tcsetattr(self.fd, libc::TCSANOW, &self.original);
+        }
+    }
+}
+
+pub fn is_tty(fd: RawFd) -> bool {
+    unsafe { libc::isatty(fd) != 0 }
+}
+
+/// Blocks `SIGWINCH`/`SIGINT`/`SIGTERM` on the calling thread (inherited by
+/// the watcher thread spawned below) and hands each one to that thread via
+/// `sigwait`, so the blocking `krun_start_enter` call never has to poll
+/// anything. Must run before any other thread that could field these
+/// signals is spawned.
+fn watch_signals(fd: RawFd, original_termios: libc::termios) -> Result<Arc<Mutex<Option<libc::winsize>>>> {
+    let mut set: libc::sigset_t = unsafe { std::mem::zeroed() };
+    unsafe {
+        libc::sigemptyset(&mut set);
+        libc::sigaddset(&mut set, libc::SIGWINCH);
+        libc::sigaddset(&mut set, libc::SIGINT);
+        libc::sigaddset(&mut set, libc::SIGTERM);
+    }
+    let rc = unsafe { libc::pthread_sigmask(libc::SIG_BLOCK, &set, std::ptr::null_mut()) };
+    if rc != 0 {
+        return Err(io::Error::from_raw_os_error(rc)).context("pthread_sigmask failed");
+    }
+
+    let size = Arc::new(Mutex::new(read_winsize(fd)));
+    let size_for_thread = Arc::clone(&size);
+
+    std::thread::spawn(move || {
+        loop {
+            let mut signo: libc::c_int = 0;
+            if unsafe { libc::sigwait(&set, &mut signo) } != 0 {
+                break;
+            }
+            match signo {
+                libc::SIGWINCH => {
+                    if let Ok(mut current) = size_for_thread.lock() {
+                        *current = read_winsize(fd);
+                    }
+                }
+                libc::SIGINT | libc::SIGTERM => {
+                    unsafe {
+                        libc::tcsetattr(fd, libc::TCSANOW, &original_termios);
+                    }
+                    std::process::exit(128 + signo);
+                }
+                _ => {}
+            }
+        }
+    });
+
+    Ok(size)
+}
+
+fn read_winsize(fd: RawFd) -> Option<libc::winsize> {
+    let mut ws: libc::winsize = unsafe { std::mem::zeroed() };
+    if unsafe { libc::ioctl(fd, libc::TIOCGWINSZ, &mut ws) } == 0 {
+        Some(ws)
+    } else {
+        None
+    }
+}
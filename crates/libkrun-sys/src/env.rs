@@ -0,0 +1,149 @@
+use std::collections::BTreeMap;
+
+/// How much of the host environment to carry into the guest `exec`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum EnvPolicy {
+    /// Pass nothing through from the host; only explicit overrides apply.
+    #[default]
+    InheritNone,
+    /// Pass everything through from the host.
+    InheritAll,
+    /// Pass through only the named variables.
+    InheritAllowlist(Vec<String>),
+    /// Pass through everything except the named variables.
+    InheritDenylist(Vec<String>),
+}
+
+/// Builds the final `KEY=VALUE` vector for `krun_set_exec` from a host
+/// environment plus an inheritance policy, with explicit overrides always
+/// winning over whatever was inherited. This is the common "pass through
+/// `TERM`/`LANG`/`PATH` but force `HOME=/root`" shape for running interactive
+/// tools inside the microVM.
+#[derive(Debug, Clone, Default)]
+pub struct EnvSpec {
+    policy: EnvPolicy,
+    overrides: Vec<(String, String)>,
+}
+
+impl EnvSpec {
+    pub fn new(policy: EnvPolicy) -> Self {
+        Self {
+            policy,
+            overrides: Vec::new(),
+        }
+    }
+
+    pub fn inherit_all() -> Self {
+        Self::new(EnvPolicy::InheritAll)
+    }
+
+    pub fn inherit_allowlist<I, S>(names: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self::new(EnvPolicy::InheritAllowlist(
+            names.into_iter().map(Into::into).collect(),
+        ))
+    }
+
+    pub fn inherit_denylist<I, S>(names: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self::new(EnvPolicy::InheritDenylist(
+            names.into_iter().map(Into::into).collect(),
+        ))
+    }
+
+    #[must_use]
+    pub fn with_override(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.overrides.push((key.into(), value.into()));
+        self
+    }
+
+    #[must_use]
+    pub fn with_policy(mut self, policy: EnvPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Composes `host_env` (normally `std::env::vars()`) with this spec's
+    /// policy and overrides into the `KEY=VALUE` vector krun expects, sorted
+    /// by key for deterministic output.
+    pub fn build<I>(&self, host_env: I) -> Vec<String>
+    where
+        I: IntoIterator<Item = (String, String)>,
+    {
+        let mut resolved: BTreeMap<String, String> = match &self.policy {
+            EnvPolicy::InheritNone => BTreeMap::new(),
+            EnvPolicy::InheritAll => host_env.into_iter().collect(),
+            EnvPolicy::InheritAllowlist(names) => host_env
+                .into_iter()
+                .filter(|(key, _)| names.iter().any(|name| name == key))
+                .collect(),
+            EnvPolicy::InheritDenylist(names) => host_env
+                .into_iter()
+                .filter(|(key, _)| !names.iter().any(|name| name == key))
+                .collect(),
+        };
+
+        for (key, value) in &self.overrides {
+            resolved.insert(key.clone(), value.clone());
+        }
+
+        resolved
+            .into_iter()
+            .map(|(key, value)| format!("{key}={value}"))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn host_env() -> Vec<(String, String)> {
+        vec![
+            ("TERM".to_string(), "xterm-256color".to_string()),
+            ("LANG".to_string(), "en_US.UTF-8".to_string()),
+            ("SECRET_TOKEN".to_string(), "do-not-leak".to_string()),
+        ]
+    }
+
+    #[test]
+    fn inherit_none_only_applies_overrides() {
+        let spec = EnvSpec::new(EnvPolicy::InheritNone).with_override("HOME", "/root");
+        assert_eq!(spec.build(host_env()), vec!["HOME=/root".to_string()]);
+    }
+
+    #[test]
+    fn inherit_allowlist_passes_through_only_named_vars() {
+        let spec = EnvSpec::inherit_allowlist(["TERM", "LANG"]).with_override("HOME", "/root");
+        let env = spec.build(host_env());
+        assert_eq!(
+            env,
+            vec![
+                "HOME=/root".to_string(),
+                "LANG=en_US.UTF-8".to_string(),
+                "TERM=xterm-256color".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn inherit_denylist_drops_named_vars() {
+        let spec = EnvSpec::inherit_denylist(["SECRET_TOKEN"]);
+        let env = spec.build(host_env());
+        assert!(!env.iter().any(|entry| entry.starts_with("SECRET_TOKEN=")));
+        assert!(env.iter().any(|entry| entry.starts_with("TERM=")));
+    }
+
+    #[test]
+    fn overrides_win_over_inherited_values() {
+        let spec = EnvSpec::inherit_all().with_override("TERM", "dumb");
+        let env = spec.build(host_env());
+        assert!(env.contains(&"TERM=dumb".to_string()));
+    }
+}
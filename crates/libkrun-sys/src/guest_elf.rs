@@ -0,0 +1,159 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, bail};
+use goblin::elf::Elf;
+use goblin::elf::header::{EM_AARCH64, EM_X86_64, machine_to_str};
+
+const DEFAULT_LIBRARY_DIRS: &[&str] = &["lib", "lib64", "usr/lib", "usr/lib64"];
+
+/// Debian/Ubuntu multiarch library directories, keyed by `e_machine`. A
+/// standard Ubuntu cloud image rootfs — what this project fetches by default
+/// — keeps `libc.so.6` and friends under `lib/<triple>`, not plain `lib/`, so
+/// these have to be searched too or every stock binary looks "broken".
+const MULTIARCH_TRIPLES: &[(u16, &str)] = &[
+    (EM_X86_64, "x86_64-linux-gnu"),
+    (EM_AARCH64, "aarch64-linux-gnu"),
+];
+
+/// Validates a guest executable before handing control to
+/// `krun_start_enter`, where a bad binary just exits the VM instantly with
+/// no message. Checks the ELF machine type against the host/krun build,
+/// that the `PT_INTERP` loader (if any) resolves under the rootfs, and that
+/// every `DT_NEEDED` shared library resolves somewhere under the rootfs, via
+/// `DT_RPATH`/`DT_RUNPATH` (with `$ORIGIN` expanded), the standard library
+/// directories, and the Debian/Ubuntu multiarch directories for the guest's
+/// architecture.
+pub fn validate_guest_executable(rootfs: &Path, exec_path: &str) -> Result<()> {
+    let full_path = rootfs.join(exec_path.trim_start_matches('/'));
+    let bytes = fs::read(&full_path)
+        .with_context(|| format!("failed to read guest executable {}", full_path.display()))?;
+    let elf = Elf::parse(&bytes)
+        .with_context(|| format!("failed to parse ELF header for {}", full_path.display()))?;
+
+    let host_machine = host_elf_machine();
+    if host_machine != 0 && elf.header.e_machine != host_machine {
+        bail!(
+            "guest executable {} is built for {} but this krun build targets {}",
+            full_path.display(),
+            machine_to_str(elf.header.e_machine),
+            machine_to_str(host_machine),
+        );
+    }
+
+    if let Some(interp) = elf.interpreter {
+        let interp_path = rootfs.join(interp.trim_start_matches('/'));
+        if !interp_path.exists() {
+            bail!(
+                "guest executable {} specifies ELF interpreter {} which is missing under the rootfs",
+                full_path.display(),
+                interp
+            );
+        }
+    }
+
+    let exec_dir = full_path.parent().unwrap_or(rootfs);
+    let search_dirs = library_search_dirs(&elf, exec_dir, rootfs);
+
+    let missing: Vec<&str> = elf
+        .libraries
+        .iter()
+        .copied()
+        .filter(|needed| !resolve_library(needed, &search_dirs, rootfs))
+        .collect();
+
+    if !missing.is_empty() {
+        bail!(
+            "guest executable {} is missing shared libraries under the rootfs: {}",
+            full_path.display(),
+            missing.join(", ")
+        );
+    }
+
+    Ok(())
+}
+
+fn host_elf_machine() -> u16 {
+    if cfg!(target_arch = "x86_64") {
+        EM_X86_64
+    } else if cfg!(target_arch = "aarch64") {
+        EM_AARCH64
+    } else {
+        0
+    }
+}
+
+fn library_search_dirs(elf: &Elf, exec_dir: &Path, rootfs: &Path) -> Vec<PathBuf> {
+    // `$ORIGIN` is the guest-side directory; `rootfs` is joined back on below.
+    let origin = match exec_dir.strip_prefix(rootfs) {
+        Ok(relative) => format!("/{}", relative.to_string_lossy()),
+        Err(_) => exec_dir.to_string_lossy().into_owned(),
+    };
+    let mut dirs: Vec<PathBuf> = elf
+        .rpaths
+        .iter()
+        .chain(elf.runpaths.iter())
+        .flat_map(|entry| entry.split(':'))
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let expanded = part.replace("$ORIGIN", &origin);
+            rootfs.join(expanded.trim_start_matches('/'))
+        })
+        .collect();
+
+    dirs.extend(DEFAULT_LIBRARY_DIRS.iter().map(|dir| rootfs.join(dir)));
+    dirs.extend(multiarch_library_dirs(elf.header.e_machine, rootfs));
+    dirs
+}
+
+fn multiarch_library_dirs(machine: u16, rootfs: &Path) -> Vec<PathBuf> {
+    MULTIARCH_TRIPLES
+        .iter()
+        .find(|(candidate, _)| *candidate == machine)
+        .map(|(_, triple)| {
+            vec![
+                rootfs.join("lib").join(triple),
+                rootfs.join("usr").join("lib").join(triple),
+            ]
+        })
+        .unwrap_or_default()
+}
+
+fn resolve_library(name: &str, search_dirs: &[PathBuf], rootfs: &Path) -> bool {
+    if let Some(absolute) = name.strip_prefix('/') {
+        return rootfs.join(absolute).exists();
+    }
+    search_dirs.iter().any(|dir| dir.join(name).exists())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolves_library_from_default_dir() {
+        let dir = std::env::temp_dir().join(format!("krunclaw-elf-test-{}", std::process::id()));
+        let lib_dir = dir.join("lib");
+        std::fs::create_dir_all(&lib_dir).unwrap();
+        std::fs::write(lib_dir.join("libc.so.6"), b"").unwrap();
+
+        let dirs = vec![lib_dir];
+        assert!(resolve_library("libc.so.6", &dirs, &dir));
+        assert!(!resolve_library("libmissing.so", &dirs, &dir));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn multiarch_dirs_match_known_machines_only() {
+        let rootfs = Path::new("/rootfs");
+        let x86_64 = multiarch_library_dirs(EM_X86_64, rootfs);
+        assert!(x86_64.contains(&rootfs.join("lib/x86_64-linux-gnu")));
+        assert!(x86_64.contains(&rootfs.join("usr/lib/x86_64-linux-gnu")));
+
+        let aarch64 = multiarch_library_dirs(EM_AARCH64, rootfs);
+        assert!(aarch64.contains(&rootfs.join("lib/aarch64-linux-gnu")));
+
+        assert!(multiarch_library_dirs(0, rootfs).is_empty());
+    }
+}
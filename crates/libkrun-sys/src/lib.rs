@@ -4,6 +4,14 @@ use std::path::Path;
 use anyhow::{Context, Result, anyhow, bail};
 use libloading::{Library, Symbol};
 
+pub mod console;
+pub mod env;
+pub mod guest_elf;
+pub mod mount;
+pub mod virtiofs;
+
+pub use env::{EnvPolicy, EnvSpec};
+
 type KRunCreateCtxFn = unsafe extern "C" fn() -> i32;
 type KRunFreeCtxFn = unsafe extern "C" fn(u32) -> i32;
 type KRunSetVmConfigFn = unsafe extern "C" fn(u32, u8, u32) -> i32;
@@ -85,6 +93,13 @@ pub struct RunSpec {
     pub virtiofs_mounts: Vec<(String, String)>,
     pub port_map: Vec<(u16, u16)>,
     pub exec: ExecSpec,
+    /// Whether this is an interactive foreground launch sharing the host's
+    /// controlling terminal. When set (and stdin is actually a tty), `run`
+    /// switches the terminal to raw mode and watches `SIGWINCH`/`SIGINT`/
+    /// `SIGTERM` for the duration of `krun_start_enter` (see
+    /// `console::ConsoleGuard`); batch/non-interactive launches are
+    /// unaffected either way.
+    pub interactive: bool,
 }
 
 impl LibKrun {
@@ -124,17 +139,30 @@ impl LibKrun {
                 if !Path::new(rootfs).exists() {
                     bail!("rootfs path does not exist: {rootfs}");
                 }
+                guest_elf::validate_guest_executable(Path::new(rootfs), &spec.exec.exec_path)
+                    .context("guest executable preflight check failed")?;
             }
-            RootSpec::DiskImage { disk_path, .. } => {
+            RootSpec::DiskImage {
+                disk_path,
+                read_only,
+                ..
+            } => {
                 if !Path::new(disk_path).exists() {
                     bail!("disk image path does not exist: {disk_path}");
                 }
+                check_disk_mount_safety(Path::new(disk_path), *read_only)?;
             }
         }
 
+        let resolved_mounts = virtiofs::resolve_virtiofs_mounts(&spec.virtiofs_mounts)
+            .context("virtiofs mount table is invalid")?;
+        for mount in &resolved_mounts {
+            check_virtiofs_mount_safety(&mount.tag, &mount.host_path)?;
+        }
+
         let ctx_id = unsafe { (self.fns.create_ctx)() };
         if ctx_id < 0 {
-            bail!("krun_create_ctx failed with {ctx_id}");
+            return Err(KrunCallError::new(KrunStage::Config, "krun_create_ctx", ctx_id).into());
         }
         let ctx_id = ctx_id as u32;
 
@@ -142,6 +170,7 @@ impl LibKrun {
             call_krun(
                 unsafe { (self.fns.set_vm_config)(ctx_id, spec.cpus, spec.memory_mib) },
                 "krun_set_vm_config",
+                KrunStage::Config,
             )?;
 
             match &spec.root {
@@ -151,6 +180,7 @@ impl LibKrun {
                     call_krun(
                         unsafe { (self.fns.set_root)(ctx_id, c_root.as_ptr()) },
                         "krun_set_root",
+                        KrunStage::Root,
                     )?;
                 }
                 RootSpec::DiskImage {
@@ -184,6 +214,7 @@ impl LibKrun {
                             )
                         },
                         "krun_add_disk2",
+                        KrunStage::Disk,
                     )?;
 
                     let c_device = CString::new(root_device.as_str())
@@ -216,18 +247,21 @@ impl LibKrun {
                             )
                         },
                         "krun_set_root_disk_remount",
+                        KrunStage::Disk,
                     )?;
                 }
             }
 
-            for (tag, host_path) in &spec.virtiofs_mounts {
-                let c_tag = CString::new(tag.as_str())
-                    .with_context(|| format!("virtiofs tag contains null byte: {tag}"))?;
-                let c_path = CString::new(host_path.as_str())
+            for mount in &resolved_mounts {
+                let c_tag = CString::new(mount.tag.as_str())
+                    .with_context(|| format!("virtiofs tag contains null byte: {}", mount.tag))?;
+                let host_path = mount.host_path.to_string_lossy();
+                let c_path = CString::new(host_path.as_ref())
                     .with_context(|| format!("virtiofs path contains null byte: {host_path}"))?;
                 call_krun(
                     unsafe { (self.fns.add_virtiofs)(ctx_id, c_tag.as_ptr(), c_path.as_ptr()) },
                     "krun_add_virtiofs",
+                    KrunStage::Mount,
                 )?;
             }
 
@@ -235,6 +269,7 @@ impl LibKrun {
             call_krun(
                 unsafe { (self.fns.set_port_map)(ctx_id, port_map.as_ptr()) },
                 "krun_set_port_map",
+                KrunStage::Config,
             )?;
 
             let workdir =
@@ -242,6 +277,7 @@ impl LibKrun {
             call_krun(
                 unsafe { (self.fns.set_workdir)(ctx_id, workdir.as_ptr()) },
                 "krun_set_workdir",
+                KrunStage::Exec,
             )?;
 
             let exec_path = CString::new(spec.exec.exec_path.as_str())
@@ -254,11 +290,22 @@ impl LibKrun {
                     (self.fns.set_exec)(ctx_id, exec_path.as_ptr(), argv.as_ptr(), env.as_ptr())
                 },
                 "krun_set_exec",
+                KrunStage::Exec,
             )?;
 
+            let _console_guard = if spec.interactive && console::is_tty(libc::STDIN_FILENO) {
+                Some(
+                    console::ConsoleGuard::enable(libc::STDIN_FILENO)
+                        .context("failed to prepare interactive console")?,
+                )
+            } else {
+                None
+            };
+
             call_krun(
                 unsafe { (self.fns.start_enter)(ctx_id) },
                 "krun_start_enter",
+                KrunStage::Start,
             )?;
 
             Ok(())
@@ -339,11 +386,126 @@ impl CStringArray {
     }
 }
 
-fn call_krun(code: c_int, name: &str) -> Result<()> {
+/// Guards against the classic "image mounted twice" footgun: if the host is
+/// already loop-mounting `disk_path` read-write, handing the same file to
+/// krun as a writable block device risks silent corruption from two
+/// uncoordinated writers.
+fn check_disk_mount_safety(disk_path: &Path, read_only: bool) -> Result<()> {
+    let Some(entry) = mount::is_source_mounted(disk_path)? else {
+        return Ok(());
+    };
+
+    let message = format!(
+        "disk image {} appears to already be mounted at {} (fstype {}); running it as a VM disk risks corrupting the mount",
+        disk_path.display(),
+        entry.target,
+        entry.fstype
+    );
+
+    if read_only || !entry.is_read_write() {
+        eprintln!("warning: {message}");
+        Ok(())
+    } else {
+        Err(anyhow!("{message}"))
+    }
+}
+
+/// Warns when a virtiofs host source is itself a mountpoint, since host-side
+/// writes to whatever is mounted there may not be what the guest expects to
+/// see under that tag.
+fn check_virtiofs_mount_safety(tag: &str, host_path: &Path) -> Result<()> {
+    if let Some(entry) = mount::is_target_mounted(host_path)? {
+        eprintln!(
+            "warning: virtiofs source for tag '{tag}' ({}) is itself a mountpoint (fstype {}); host-side changes there may not propagate as expected",
+            host_path.display(),
+            entry.fstype
+        );
+    }
+    Ok(())
+}
+
+/// Which stage of setting up the VM a failing libkrun call belongs to, so
+/// callers can match on what went wrong instead of string-matching the error
+/// message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KrunStage {
+    Config,
+    Root,
+    Disk,
+    Mount,
+    Exec,
+    Start,
+}
+
+impl std::fmt::Display for KrunStage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let label = match self {
+            Self::Config => "config",
+            Self::Root => "root",
+            Self::Disk => "disk",
+            Self::Mount => "mount",
+            Self::Exec => "exec",
+            Self::Start => "start",
+        };
+        f.write_str(label)
+    }
+}
+
+/// A failing libkrun call, with the negative return code decoded as `-errno`
+/// when possible. libkrun returns `-errno` on failure, so `"-13"` is far less
+/// useful to a user than the OS description, `"Permission denied (os error 13)"`.
+#[derive(Debug)]
+pub struct KrunCallError {
+    pub stage: KrunStage,
+    pub call: &'static str,
+    pub code: c_int,
+}
+
+impl KrunCallError {
+    fn new(stage: KrunStage, call: &'static str, code: c_int) -> Self {
+        Self { stage, call, code }
+    }
+
+    fn errno_description(&self) -> Option<String> {
+        decode_errno(self.code)
+    }
+}
+
+impl std::fmt::Display for KrunCallError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.errno_description() {
+            Some(description) => write!(
+                f,
+                "{} failed: {description} ({} stage)",
+                self.call, self.stage
+            ),
+            None => write!(
+                f,
+                "{} failed with code {} ({} stage)",
+                self.call, self.code, self.stage
+            ),
+        }
+    }
+}
+
+impl std::error::Error for KrunCallError {}
+
+/// Interprets a negative libkrun return code as `-errno`, returning the OS's
+/// description of it. Codes that don't map to a known errno still round-trip
+/// through `std::io::Error`'s "Unknown error N" fallback, so this only
+/// returns `None` for non-negative codes.
+fn decode_errno(code: c_int) -> Option<String> {
+    if code >= 0 {
+        return None;
+    }
+    Some(std::io::Error::from_raw_os_error(code.saturating_neg()).to_string())
+}
+
+fn call_krun(code: c_int, name: &'static str, stage: KrunStage) -> Result<(), KrunCallError> {
     if code == 0 {
         Ok(())
     } else {
-        Err(anyhow!("{name} failed with {code}"))
+        Err(KrunCallError::new(stage, name, code))
     }
 }
 
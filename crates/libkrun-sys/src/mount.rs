@@ -0,0 +1,183 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+
+/// One parsed line of `/proc/mounts` (`fstab(5)` format): source, target,
+/// filesystem type, and comma-separated mount options.
+#[derive(Debug, Clone)]
+pub struct MountEntry {
+    pub source: String,
+    pub target: String,
+    pub fstype: String,
+    pub options: Vec<String>,
+}
+
+impl MountEntry {
+    pub fn is_read_write(&self) -> bool {
+        self.options.iter().any(|opt| opt == "rw")
+    }
+}
+
+/// Reads and parses `/proc/mounts`. Returns an empty list on platforms
+/// without it (e.g. macOS) rather than failing the caller's safety check.
+pub fn read_proc_mounts() -> Result<Vec<MountEntry>> {
+    let path = Path::new("/proc/mounts");
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let contents =
+        fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))?;
+    Ok(parse_mounts(&contents))
+}
+
+fn parse_mounts(contents: &str) -> Vec<MountEntry> {
+    contents
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let source = fields.next()?.to_string();
+            let target = fields.next()?.to_string();
+            let fstype = fields.next()?.to_string();
+            let options = fields
+                .next()
+                .map(|opts| opts.split(',').map(str::to_string).collect())
+                .unwrap_or_default();
+            Some(MountEntry {
+                source,
+                target,
+                fstype,
+                options,
+            })
+        })
+        .collect()
+}
+
+/// Finds the `/proc/mounts` entry, if any, whose source matches `path` (e.g.
+/// a disk image that is already loop-mounted somewhere). Checks a direct
+/// source match first, then falls back to the common loop-mount case: the
+/// backing file itself never appears in `/proc/mounts`, only the loop device
+/// (`/dev/loopN`) does, so the association has to go through
+/// `/sys/block/loopN/loop/backing_file`.
+pub fn is_source_mounted(path: &Path) -> Result<Option<MountEntry>> {
+    let target = canonicalize_best_effort(path);
+    let mounts = read_proc_mounts()?;
+
+    if let Some(entry) = mounts
+        .iter()
+        .find(|entry| canonicalize_best_effort(Path::new(&entry.source)) == target)
+    {
+        return Ok(Some(entry.clone()));
+    }
+
+    let Some(loop_device) = find_loop_device_for_backing_file(&target)? else {
+        return Ok(None);
+    };
+
+    Ok(mounts.into_iter().find(|entry| entry.source == loop_device))
+}
+
+/// Scans `/sys/block/loop*/loop/backing_file` for a loop device whose
+/// backing file resolves to `target`, returning e.g. `"/dev/loop3"`.
+fn find_loop_device_for_backing_file(target: &Path) -> Result<Option<String>> {
+    find_loop_device_for_backing_file_in(Path::new("/sys/block"), target)
+}
+
+fn find_loop_device_for_backing_file_in(sys_block: &Path, target: &Path) -> Result<Option<String>> {
+    if !sys_block.exists() {
+        return Ok(None);
+    }
+
+    let entries = fs::read_dir(sys_block)
+        .with_context(|| format!("failed to read {}", sys_block.display()))?;
+
+    for entry in entries {
+        let entry =
+            entry.with_context(|| format!("failed to read entry in {}", sys_block.display()))?;
+        let name = entry.file_name();
+        let Some(name) = name.to_str() else {
+            continue;
+        };
+        if !name.starts_with("loop") {
+            continue;
+        }
+
+        let backing_file_path = entry.path().join("loop").join("backing_file");
+        let Ok(backing_file) = fs::read_to_string(&backing_file_path) else {
+            continue;
+        };
+        let backing_file = backing_file.trim();
+        if backing_file.is_empty() {
+            continue;
+        }
+
+        if canonicalize_best_effort(Path::new(backing_file)) == target {
+            return Ok(Some(format!("/dev/{name}")));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Finds the `/proc/mounts` entry, if any, whose target matches `path`
+/// (e.g. a virtiofs host source that is itself a mountpoint).
+pub fn is_target_mounted(path: &Path) -> Result<Option<MountEntry>> {
+    let target = canonicalize_best_effort(path);
+    Ok(read_proc_mounts()?
+        .into_iter()
+        .find(|entry| canonicalize_best_effort(Path::new(&entry.target)) == target))
+}
+
+fn canonicalize_best_effort(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_well_formed_lines() {
+        let contents = "/dev/sda1 / ext4 rw,relatime 0 0\noverlay /var/lib/docker overlay ro,lowerdir=a 0 0\n";
+        let entries = parse_mounts(contents);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].source, "/dev/sda1");
+        assert_eq!(entries[0].target, "/");
+        assert!(entries[0].is_read_write());
+        assert!(!entries[1].is_read_write());
+    }
+
+    #[test]
+    fn skips_malformed_lines() {
+        let entries = parse_mounts("garbage\n/dev/sda1 / ext4 rw 0 0\n");
+        assert_eq!(entries.len(), 1);
+    }
+
+    #[test]
+    fn finds_loop_device_by_backing_file() {
+        let sys_block = std::env::temp_dir().join(format!(
+            "krunclaw-mount-test-sysblock-{}",
+            std::process::id()
+        ));
+        let loop_dir = sys_block.join("loop7").join("loop");
+        fs::create_dir_all(&loop_dir).unwrap();
+
+        let backing = std::env::temp_dir().join(format!(
+            "krunclaw-mount-test-backing-{}.img",
+            std::process::id()
+        ));
+        fs::write(&backing, b"disk").unwrap();
+        fs::write(loop_dir.join("backing_file"), backing.display().to_string()).unwrap();
+        let backing = backing.canonicalize().unwrap();
+
+        let found = find_loop_device_for_backing_file_in(&sys_block, &backing).unwrap();
+        assert_eq!(found, Some("/dev/loop7".to_string()));
+
+        let miss = find_loop_device_for_backing_file_in(&sys_block, Path::new("/no/such/file"))
+            .unwrap();
+        assert_eq!(miss, None);
+
+        let _ = fs::remove_dir_all(&sys_block);
+        let _ = fs::remove_file(&backing);
+    }
+}
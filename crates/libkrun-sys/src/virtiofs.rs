@@ -0,0 +1,213 @@
+use std::collections::HashSet;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// A `virtiofs_mounts` entry after resolution: the guest tag paired with the
+/// canonical (symlink-resolved) host path it points at.
+#[derive(Debug, Clone)]
+pub struct ResolvedMount {
+    pub tag: String,
+    pub host_path: PathBuf,
+}
+
+/// Why a virtiofs mount table failed to resolve. Kept as a typed enum rather
+/// than folding straight into `anyhow::Error` so a caller can match on which
+/// rule tripped instead of scraping the message.
+#[derive(Debug)]
+pub enum MountError {
+    NotAbsolute {
+        tag: String,
+        path: String,
+    },
+    InvalidPath {
+        tag: String,
+        path: String,
+        source: std::io::Error,
+    },
+    Recursion {
+        tag: String,
+        path: String,
+    },
+    DuplicateTag {
+        tag: String,
+    },
+    Overlapping {
+        tag: String,
+        other_tag: String,
+        path: PathBuf,
+        other_path: PathBuf,
+    },
+}
+
+impl fmt::Display for MountError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotAbsolute { tag, path } => write!(
+                f,
+                "virtiofs mount '{tag}' has a relative host path '{path}'; only absolute paths are supported"
+            ),
+            Self::InvalidPath { tag, path, source } => write!(
+                f,
+                "virtiofs mount '{tag}' host path '{path}' could not be resolved: {source}"
+            ),
+            Self::Recursion { tag, path } => write!(
+                f,
+                "virtiofs mount '{tag}' host path '{path}' could not be resolved: symlink resolution looped"
+            ),
+            Self::DuplicateTag { tag } => {
+                write!(f, "virtiofs guest tag '{tag}' is used by more than one mount")
+            }
+            Self::Overlapping {
+                tag,
+                other_tag,
+                path,
+                other_path,
+            } => write!(
+                f,
+                "virtiofs mounts '{tag}' ({}) and '{other_tag}' ({}) overlap; one host path is a prefix of the other, which would shadow files in the guest",
+                path.display(),
+                other_path.display()
+            ),
+        }
+    }
+}
+
+impl std::error::Error for MountError {}
+
+/// Resolves and validates a `virtiofs_mounts` table before it reaches
+/// `krun_add_virtiofs`: host paths must be absolute and must canonicalize
+/// (following symlinks, erroring on a resolution loop), guest tags must be
+/// unique, and no two host paths may nest inside one another (which would
+/// silently shadow part of a mount inside the guest).
+pub fn resolve_virtiofs_mounts(
+    mounts: &[(String, String)],
+) -> Result<Vec<ResolvedMount>, MountError> {
+    let mut resolved = Vec::with_capacity(mounts.len());
+    let mut seen_tags = HashSet::with_capacity(mounts.len());
+
+    for (tag, host_path) in mounts {
+        if !seen_tags.insert(tag.as_str()) {
+            return Err(MountError::DuplicateTag { tag: tag.clone() });
+        }
+
+        let path = Path::new(host_path);
+        if !path.is_absolute() {
+            return Err(MountError::NotAbsolute {
+                tag: tag.clone(),
+                path: host_path.clone(),
+            });
+        }
+
+        let canonical = path.canonicalize().map_err(|source| {
+            if source.raw_os_error() == Some(libc::ELOOP) {
+                MountError::Recursion {
+                    tag: tag.clone(),
+                    path: host_path.clone(),
+                }
+            } else {
+                MountError::InvalidPath {
+                    tag: tag.clone(),
+                    path: host_path.clone(),
+                    source,
+                }
+            }
+        })?;
+
+        resolved.push(ResolvedMount {
+            tag: tag.clone(),
+            host_path: canonical,
+        });
+    }
+
+    check_overlaps(&resolved)?;
+    Ok(resolved)
+}
+
+fn check_overlaps(mounts: &[ResolvedMount]) -> Result<(), MountError> {
+    for (index, mount) in mounts.iter().enumerate() {
+        for other in &mounts[index + 1..] {
+            if mount.host_path.starts_with(&other.host_path) || other.host_path.starts_with(&mount.host_path)
+            {
+                return Err(MountError::Overlapping {
+                    tag: mount.tag.clone(),
+                    other_tag: other.tag.clone(),
+                    path: mount.host_path.clone(),
+                    other_path: other.host_path.clone(),
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "krunclaw-virtiofs-test-{}-{name}",
+            std::process::id()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn rejects_relative_host_paths() {
+        let err = resolve_virtiofs_mounts(&[("workspace".to_string(), "relative/path".to_string())])
+            .unwrap_err();
+        assert!(matches!(err, MountError::NotAbsolute { .. }));
+    }
+
+    #[test]
+    fn rejects_missing_host_paths() {
+        let err = resolve_virtiofs_mounts(&[(
+            "workspace".to_string(),
+            "/nonexistent/krunclaw-test-path".to_string(),
+        )])
+        .unwrap_err();
+        assert!(matches!(err, MountError::InvalidPath { .. }));
+    }
+
+    #[test]
+    fn rejects_duplicate_tags() {
+        let dir = temp_dir("dup");
+        let mounts = vec![
+            ("workspace".to_string(), dir.display().to_string()),
+            ("workspace".to_string(), dir.display().to_string()),
+        ];
+        let err = resolve_virtiofs_mounts(&mounts).unwrap_err();
+        assert!(matches!(err, MountError::DuplicateTag { .. }));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn rejects_nested_host_paths() {
+        let dir = temp_dir("nested");
+        let nested = dir.join("child");
+        std::fs::create_dir_all(&nested).unwrap();
+
+        let mounts = vec![
+            ("outer".to_string(), dir.display().to_string()),
+            ("inner".to_string(), nested.display().to_string()),
+        ];
+        let err = resolve_virtiofs_mounts(&mounts).unwrap_err();
+        assert!(matches!(err, MountError::Overlapping { .. }));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn accepts_disjoint_absolute_paths() {
+        let a = temp_dir("a");
+        let b = temp_dir("b");
+        let mounts = vec![
+            ("a".to_string(), a.display().to_string()),
+            ("b".to_string(), b.display().to_string()),
+        ];
+        let resolved = resolve_virtiofs_mounts(&mounts).unwrap();
+        assert_eq!(resolved.len(), 2);
+        let _ = std::fs::remove_dir_all(&a);
+        let _ = std::fs::remove_dir_all(&b);
+    }
+}